@@ -0,0 +1,134 @@
+use ffi;
+use std::c_str::CString;
+use std::libc;
+use std::mem;
+
+pub enum YamlNode {
+    YamlScalarNode(Option<~str>, ~[u8], ffi::yaml_scalar_style_t),
+    YamlSequenceNode(Option<~str>, ~[int]),
+    YamlMappingNode(Option<~str>, ~[(int, int)]),
+}
+
+pub struct YamlDocument {
+    document_mem: ffi::yaml_document_t,
+}
+
+impl YamlDocument {
+    pub fn new(document_mem: ffi::yaml_document_t) -> ~YamlDocument {
+        ~YamlDocument {
+            document_mem: document_mem
+        }
+    }
+
+    unsafe fn get_node(&self, node_id: int) -> *ffi::yaml_node_t {
+        ffi::yaml_document_get_node(&self.document_mem, node_id as libc::c_int)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unsafe {
+            ffi::yaml_document_get_root_node(&self.document_mem).is_null()
+        }
+    }
+
+    pub fn root(&self) -> Option<YamlNode> {
+        match self.root_id() {
+            Some(id) => self.load(id),
+            None => None
+        }
+    }
+
+    // Node ids are 1-based indices into the document's node stack. Rather than
+    // assume the root was appended first, derive its id from the pointer
+    // `yaml_document_get_root_node` hands back.
+    pub fn root_id(&self) -> Option<int> {
+        unsafe {
+            let root = ffi::yaml_document_get_root_node(&self.document_mem);
+            if root.is_null() {
+                return None;
+            }
+            let start = self.document_mem.nodes.start;
+            let offset = (root as uint - start as uint) / mem::size_of::<ffi::yaml_node_t>();
+            Some(offset as int + 1)
+        }
+    }
+
+    // Sequence items and mapping pairs are returned as node ids rather than
+    // inlined nodes. Because libyaml resolves every `*alias` to the node id of
+    // its `&anchor` definition during `yaml_parser_load`, addressing by id gives
+    // callers a shared-structure graph instead of duplicated values.
+    pub fn load(&self, node_id: int) -> Option<YamlNode> {
+        unsafe {
+            let node = self.get_node(node_id);
+            if node.is_null() {
+                return None;
+            }
+
+            let tag = tag_of(node);
+
+            match (*node).node_type {
+                ffi::YAML_SCALAR_NODE => {
+                    let data = (*node).data.scalar();
+                    let value = std::slice::raw::buf_as_slice(data.value, data.length as uint, |s| s.to_owned());
+                    Some(YamlScalarNode(tag, value, data.style))
+                },
+                ffi::YAML_SEQUENCE_NODE => {
+                    let data = (*node).data.sequence();
+                    let mut items = ~[];
+                    let mut item = data.items.start;
+                    while item < data.items.top {
+                        items.push(*item as int);
+                        item = item.offset(1);
+                    }
+                    Some(YamlSequenceNode(tag, items))
+                },
+                ffi::YAML_MAPPING_NODE => {
+                    let data = (*node).data.mapping();
+                    let mut pairs = ~[];
+                    let mut pair = data.pairs.start;
+                    while pair < data.pairs.top {
+                        pairs.push(((*pair).key as int, (*pair).value as int));
+                        pair = pair.offset(1);
+                    }
+                    Some(YamlMappingNode(tag, pairs))
+                },
+                _ => None
+            }
+        }
+    }
+}
+
+unsafe fn tag_of(node: *ffi::yaml_node_t) -> Option<~str> {
+    CString::new((*node).tag as *libc::c_char, false).as_str().map(|s| s.into_owned())
+}
+
+impl Drop for YamlDocument {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::yaml_document_delete(&mut self.document_mem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use parser;
+    use parser::YamlParser;
+    use document::YamlSequenceNode;
+
+    #[test]
+    fn test_alias_resolution() {
+        let data = "- &a 1\n- *a\n";
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
+        let doc = parser.load().unwrap();
+
+        match doc.root() {
+            Some(YamlSequenceNode(_, items)) => {
+                assert_eq!(2, items.len());
+                // The alias `*a` must resolve to the same node id as `&a`, so
+                // the two sequence items share structure instead of duplicating.
+                assert_eq!(items[0], items[1]);
+            },
+            other => fail!("expected a sequence node, got {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use ffi;
+use event::*;
+use parser::{YamlError, YamlMark};
+use parser::{YamlNoError, YamlMemoryError, YamlReaderError, YamlScannerError};
+use parser::{YamlParserError, YamlComposerError, YamlWriterError, YamlEmitterError};
+use std::cast;
+use std::libc;
+use std::ptr;
+
+extern fn handle_writer_cb(data: *mut YamlEmitter, buffer: *u8, size: libc::size_t) -> libc::c_int {
+    unsafe {
+        let emitter = &mut *data;
+        let buf = std::slice::raw::buf_as_slice(buffer, size as uint, |s| s.to_owned());
+        match emitter.writer.write(buf) {
+            Ok(()) => 1,
+            Err(_) => 0
+        }
+    }
+}
+
+pub struct YamlEmitter {
+    emitter_mem: ffi::yaml_emitter_t,
+    writer: ~Writer,
+}
+
+impl YamlEmitter {
+    pub fn init(writer: ~Writer) -> Result<~YamlEmitter, YamlError> {
+        let mut emitter = ~YamlEmitter {
+            emitter_mem: ffi::yaml_emitter_t::new(),
+            writer: writer
+        };
+
+        unsafe {
+            if ffi::yaml_emitter_initialize(&mut emitter.emitter_mem) == 0 {
+                return Err(YamlError::memory("failed to initialize yaml_emitter_t"));
+            }
+
+            ffi::yaml_emitter_set_output(&mut emitter.emitter_mem, handle_writer_cb, cast::transmute(&mut *emitter));
+        }
+
+        Ok(emitter)
+    }
+
+    pub fn set_canonical(&mut self, canonical: bool) {
+        unsafe {
+            ffi::yaml_emitter_set_canonical(&mut self.emitter_mem, canonical as libc::c_int);
+        }
+    }
+
+    pub fn set_unicode(&mut self, unicode: bool) {
+        unsafe {
+            ffi::yaml_emitter_set_unicode(&mut self.emitter_mem, unicode as libc::c_int);
+        }
+    }
+
+    pub fn set_indent(&mut self, indent: int) {
+        unsafe {
+            ffi::yaml_emitter_set_indent(&mut self.emitter_mem, indent as libc::c_int);
+        }
+    }
+
+    pub fn set_width(&mut self, width: int) {
+        unsafe {
+            ffi::yaml_emitter_set_width(&mut self.emitter_mem, width as libc::c_int);
+        }
+    }
+
+    pub fn emit(&mut self, event: YamlEvent) -> Result<(), YamlError> {
+        let mut event_mem = ffi::yaml_event_t::new();
+
+        unsafe {
+            match event {
+                YamlStreamStartEvent(encoding) => {
+                    ffi::yaml_stream_start_event_initialize(&mut event_mem, encoding);
+                },
+                YamlStreamEndEvent => {
+                    ffi::yaml_stream_end_event_initialize(&mut event_mem);
+                },
+                YamlDocumentStartEvent(_, _, implicit) => {
+                    ffi::yaml_document_start_event_initialize(&mut event_mem, ptr::null(), ptr::null(), ptr::null(), implicit as libc::c_int);
+                },
+                YamlDocumentEndEvent(implicit) => {
+                    ffi::yaml_document_end_event_initialize(&mut event_mem, implicit as libc::c_int);
+                },
+                YamlAliasEvent(anchor) => {
+                    anchor.with_c_str(|c_anchor| {
+                        ffi::yaml_alias_event_initialize(&mut event_mem, c_anchor);
+                    });
+                },
+                YamlScalarEvent(scalar) => {
+                    let anchor = c_str_opt(&scalar.anchor);
+                    let tag = c_str_opt(&scalar.tag);
+                    ffi::yaml_scalar_event_initialize(&mut event_mem,
+                        ptr_of(&anchor), ptr_of(&tag),
+                        scalar.value.as_ptr(), scalar.value.len() as libc::c_int,
+                        scalar.plain_implicit as libc::c_int, scalar.quoted_implicit as libc::c_int,
+                        scalar.style);
+                },
+                YamlSequenceStartEvent(seq) => {
+                    let anchor = c_str_opt(&seq.anchor);
+                    let tag = c_str_opt(&seq.tag);
+                    ffi::yaml_sequence_start_event_initialize(&mut event_mem,
+                        ptr_of(&anchor), ptr_of(&tag), seq.implicit as libc::c_int, seq.style);
+                },
+                YamlSequenceEndEvent => {
+                    ffi::yaml_sequence_end_event_initialize(&mut event_mem);
+                },
+                YamlMappingStartEvent(map) => {
+                    let anchor = c_str_opt(&map.anchor);
+                    let tag = c_str_opt(&map.tag);
+                    ffi::yaml_mapping_start_event_initialize(&mut event_mem,
+                        ptr_of(&anchor), ptr_of(&tag), map.implicit as libc::c_int, map.style);
+                },
+                YamlMappingEndEvent => {
+                    ffi::yaml_mapping_end_event_initialize(&mut event_mem);
+                },
+                YamlNoEvent => {
+                    return Ok(());
+                }
+            }
+
+            if ffi::yaml_emitter_emit(&mut self.emitter_mem, &mut event_mem) == 0 {
+                Err(self.get_error())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    unsafe fn get_error(&self) -> YamlError {
+        let kind = match self.emitter_mem.error {
+            ffi::YAML_NO_ERROR => YamlNoError,
+            ffi::YAML_MEMORY_ERROR => YamlMemoryError,
+            ffi::YAML_READER_ERROR => YamlReaderError,
+            ffi::YAML_SCANNER_ERROR => YamlScannerError,
+            ffi::YAML_PARSER_ERROR => YamlParserError,
+            ffi::YAML_COMPOSER_ERROR => YamlComposerError,
+            ffi::YAML_WRITER_ERROR => YamlWriterError,
+            ffi::YAML_EMITTER_ERROR => YamlEmitterError,
+            _ => fail!("unknown error type")
+        };
+
+        YamlError {
+            kind: kind,
+            problem: std::c_str::CString::new(self.emitter_mem.problem, false).as_str().map(|s| s.into_owned()),
+            byte_offset: 0,
+            problem_mark: YamlMark { index: 0, line: 0, column: 0 },
+            context: None,
+            context_mark: YamlMark { index: 0, line: 0, column: 0 },
+        }
+    }
+}
+
+impl Drop for YamlEmitter {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::yaml_emitter_delete(&mut self.emitter_mem);
+        }
+    }
+}
+
+fn c_str_opt(value: &Option<~str>) -> Option<std::c_str::CString> {
+    value.as_ref().map(|s| s.to_c_str())
+}
+
+fn ptr_of(value: &Option<std::c_str::CString>) -> *u8 {
+    match *value {
+        Some(ref c) => c.with_ref(|p| p as *u8),
+        None => ptr::null()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use event::*;
+    use parser;
+    use parser::YamlParser;
+    use emitter::YamlEmitter;
+    use std::io::MemWriter;
+
+    #[test]
+    fn test_emit_round_trip() {
+        let data = "[1, 2, 3]";
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
+        let mut stream = parser.parse();
+
+        let mut emitter = YamlEmitter::init(~MemWriter::new() as ~Writer).unwrap();
+
+        loop {
+            match stream.next_event() {
+                Ok(YamlNoEvent) => break,
+                Ok(evt) => emitter.emit(evt).unwrap(),
+                Err(err) => fail!("{:?}", err)
+            }
+        }
+    }
+}
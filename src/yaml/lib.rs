@@ -12,6 +12,9 @@ use std::libc;
 pub mod ffi;
 pub mod event;
 pub mod parser;
+pub mod emitter;
+pub mod document;
+pub mod test_suite;
 
 mod type_size;
 
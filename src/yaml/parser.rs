@@ -1,5 +1,6 @@
 use ffi;
-use event::{YamlEvent, InternalEvent};
+use document::YamlDocument;
+use event::{YamlEvent, InternalEvent, YamlStreamEndEvent, YamlNoEvent};
 use std::cast;
 use std::libc;
 use std::io;
@@ -48,12 +49,28 @@ pub struct YamlError {
     context_mark: YamlMark,
 }
 
+impl YamlError {
+    // A synthesized memory error. The `*_initialize` calls only fail when
+    // libyaml cannot allocate its internal buffers, and the half-built struct
+    // cannot be queried, so callers construct the error rather than read it back.
+    pub fn memory(problem: &str) -> YamlError {
+        YamlError {
+            kind: YamlMemoryError,
+            problem: Some(problem.to_owned()),
+            byte_offset: 0,
+            problem_mark: YamlMark { index: 0, line: 0, column: 0 },
+            context: None,
+            context_mark: YamlMark { index: 0, line: 0, column: 0 },
+        }
+    }
+}
+
 pub struct YamlEventStream<P> {
     parser: ~P,
 }
 
 impl<P:YamlParser> YamlEventStream<P> {
-    fn next_event(&mut self) -> Result<YamlEvent, YamlError> {
+    pub fn next_event(&mut self) -> Result<YamlEvent, YamlError> {
         unsafe {
             match self.parser.parse_event() {
                 Some(evt) => Ok(evt),
@@ -63,9 +80,26 @@ impl<P:YamlParser> YamlEventStream<P> {
     }
 }
 
+impl<P:YamlParser> Iterator<Result<YamlEvent, YamlError>> for YamlEventStream<P> {
+    fn next(&mut self) -> Option<Result<YamlEvent, YamlError>> {
+        match self.next_event() {
+            Ok(YamlStreamEndEvent) | Ok(YamlNoEvent) => None,
+            Ok(evt) => Some(Ok(evt)),
+            Err(err) => Some(Err(err))
+        }
+    }
+}
+
 pub trait YamlParser {
     unsafe fn base_parser_ref<'r>(&'r mut self) -> &'r mut YamlBaseParser;
 
+    // The verbatim input bytes between two mark indices, captured as the scalar
+    // `repr`. The result is owned so that `YamlEvent` stays free of a lifetime
+    // parameter: the byte parser copies out of its borrowed `&'r [u8]`, while
+    // the io parser copies from the buffer it accumulates. Returns `None` when
+    // the source is unavailable (the io parser unless repr capture is enabled).
+    fn source_repr(&self, start: uint, end: uint) -> Option<~[u8]>;
+
     unsafe fn parse_event(&mut self) -> Option<YamlEvent> {
         let mut event = InternalEvent {
             event_mem: ffi::yaml_event_t::new()
@@ -74,7 +108,16 @@ pub trait YamlParser {
         if !self.base_parser_ref().parse(&mut event.event_mem) {
             None
         } else {
-            Some(YamlEvent::load(&event))
+            // `repr` is only meaningful for scalars, so avoid an allocation per
+            // non-scalar event.
+            let repr = if event.event_mem.event_type == ffi::YAML_SCALAR_EVENT {
+                let start = event.event_mem.start_mark.index as uint;
+                let end = event.event_mem.end_mark.index as uint;
+                self.source_repr(start, end)
+            } else {
+                None
+            };
+            Some(YamlEvent::load(&event, repr))
         }
     }
 
@@ -83,6 +126,13 @@ pub trait YamlParser {
             parser: self,
         }
     }
+
+    fn load(~self) -> Result<~YamlDocument, YamlError> {
+        let mut parser = self;
+        unsafe {
+            parser.base_parser_ref().load()
+        }
+    }
 }
 
 extern fn handle_reader_cb(data: *mut YamlIoParser, buffer: *mut u8, size: libc::size_t, size_read: *mut libc::size_t) -> libc::c_int {
@@ -91,6 +141,11 @@ extern fn handle_reader_cb(data: *mut YamlIoParser, buffer: *mut u8, size: libc:
         let parser = &mut *data;
         match parser.reader.read(buf.as_mut_slice()) {
             Ok(size) => {
+                // Only retain consumed bytes when the caller opted into repr
+                // capture; otherwise the streaming parser stays O(window).
+                if parser.capture_repr {
+                    parser.input.push_all(buf.as_mut_slice().slice(0, size));
+                }
                 *size_read = size as libc::size_t;
                 return 1;
             },
@@ -132,6 +187,16 @@ impl YamlBaseParser {
         ffi::yaml_parser_parse(&mut self.parser_mem, event) != 0
     }
 
+    unsafe fn load(&mut self) -> Result<~YamlDocument, YamlError> {
+        let mut document = ffi::yaml_document_t::new();
+        if ffi::yaml_parser_load(&mut self.parser_mem, &mut document) == 0 {
+            ffi::yaml_document_delete(&mut document);
+            Err(self.get_error())
+        } else {
+            Ok(YamlDocument::new(document))
+        }
+    }
+
     unsafe fn get_error(&self) -> YamlError {
         let kind = match self.parser_mem.error {
             ffi::YAML_NO_ERROR => YamlNoError,
@@ -164,61 +229,94 @@ impl Drop for YamlBaseParser {
 }
 
 pub struct YamlByteParser<'r> {
-    base_parser: YamlBaseParser
+    base_parser: YamlBaseParser,
+    input: &'r [u8],
 }
 
 impl<'r> YamlParser for YamlByteParser<'r> {
     unsafe fn base_parser_ref<'r>(&'r mut self) -> &'r mut YamlBaseParser {
         &mut self.base_parser
     }
+
+    fn source_repr(&self, start: uint, end: uint) -> Option<~[u8]> {
+        if end > start && end <= self.input.len() {
+            Some(self.input.slice(start, end).to_owned())
+        } else {
+            None
+        }
+    }
 }
 
 impl<'r> YamlByteParser<'r> {
-    pub fn init(bytes: &'r [u8]) -> ~YamlByteParser<'r> {
+    pub fn init(bytes: &'r [u8]) -> Result<~YamlByteParser<'r>, YamlError> {
         let mut parser = ~YamlByteParser {
-            base_parser: YamlBaseParser::new()
+            base_parser: YamlBaseParser::new(),
+            input: bytes
         };
 
         unsafe {
             if !parser.base_parser.initialize() {
-                fail!("failed to initialize yaml_parser_t");
+                return Err(YamlError::memory("failed to initialize yaml_parser_t"));
             }
             parser.base_parser.set_input_string(bytes.as_ptr(), bytes.len());
         }
 
-        parser
+        Ok(parser)
     }
 }
 
 pub struct YamlIoParser {
     base_parser: YamlBaseParser,
     reader: ~Reader,
+    input: ~[u8],
+    capture_repr: bool,
 }
 
 impl<'r> YamlParser for YamlIoParser {
     unsafe fn base_parser_ref<'r>(&'r mut self) -> &'r mut YamlBaseParser {
         &mut self.base_parser
     }
+
+    // There is no borrowable input buffer here, so the scalar `repr` is an owned
+    // copy taken from the bytes accumulated by the reader callback. That
+    // accumulation only happens when repr capture is enabled, so the default
+    // streaming parser retains nothing.
+    fn source_repr(&self, start: uint, end: uint) -> Option<~[u8]> {
+        if self.capture_repr && end > start && end <= self.input.len() {
+            Some(self.input.slice(start, end).to_owned())
+        } else {
+            None
+        }
+    }
 }
 
 impl YamlIoParser {
-    pub fn init(reader: ~Reader) -> ~YamlIoParser {
+    // Opt into retaining the consumed input so scalar events carry their `repr`.
+    // Off by default: enabling it trades the streaming parser's bounded memory
+    // for a full copy of the input.
+    pub fn set_capture_repr(&mut self, enabled: bool) {
+        self.capture_repr = enabled;
+    }
+
+    pub fn init(reader: ~Reader) -> Result<~YamlIoParser, YamlError> {
         let mut parser = ~YamlIoParser {
             base_parser: YamlBaseParser::new(),
-            reader: reader
+            reader: reader,
+            input: ~[],
+            capture_repr: false
         };
 
         unsafe {
             if !parser.base_parser.initialize() {
-                fail!("failed to initialize yaml_parser_t");
+                return Err(YamlError::memory("failed to initialize yaml_parser_t"));
             }
 
             ffi::yaml_parser_set_input(&mut parser.base_parser.parser_mem, handle_reader_cb, cast::transmute(&mut *parser));
         }
 
-        parser
+        Ok(parser)
     }
-} 
+}
 
 #[cfg(test)]
 mod test {
@@ -231,14 +329,14 @@ mod test {
     #[test]
     fn test_byte_parser() {
         let data = "[1, 2, 3]";
-        let parser = parser::YamlByteParser::init(data.as_bytes());
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
         let expected = ~[
             YamlStreamStartEvent(ffi::YamlUtf8Encoding),
             YamlDocumentStartEvent(None, ~[], true),
             YamlSequenceStartEvent(YamlSequenceParam{anchor: None, tag: None, implicit: true, style: ffi::YamlFlowSequenceStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[51u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[49u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[50u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[51u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[51u8])}),
             YamlSequenceEndEvent,
             YamlDocumentEndEvent(true),
             YamlStreamEndEvent
@@ -264,18 +362,45 @@ mod test {
         assert_eq!(expected, produced);
     }
 
+    #[test]
+    fn test_event_iterator() {
+        let data = "[1, 2, 3]";
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
+        let expected = ~[
+            YamlStreamStartEvent(ffi::YamlUtf8Encoding),
+            YamlDocumentStartEvent(None, ~[], true),
+            YamlSequenceStartEvent(YamlSequenceParam{anchor: None, tag: None, implicit: true, style: ffi::YamlFlowSequenceStyle}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[49u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[50u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[51u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[51u8])}),
+            YamlSequenceEndEvent,
+            YamlDocumentEndEvent(true)
+        ];
+
+        let mut produced = ~[];
+        for evt in parser.parse() {
+            match evt {
+                Ok(evt) => produced.push(evt),
+                Err(err) => fail!("{:?}", err)
+            }
+        }
+
+        assert_eq!(expected, produced);
+    }
+
     #[test]
     fn test_io_parser() {
         let data = "[1, 2, 3]";
         let reader = ~io::BufReader::new(data.as_bytes());
-        let parser = parser::YamlIoParser::init(reader);
+        let mut parser = parser::YamlIoParser::init(reader).unwrap();
+        parser.set_capture_repr(true);
         let expected = ~[
             YamlStreamStartEvent(ffi::YamlUtf8Encoding),
             YamlDocumentStartEvent(None, ~[], true),
             YamlSequenceStartEvent(YamlSequenceParam{anchor: None, tag: None, implicit: true, style: ffi::YamlFlowSequenceStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[51u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[49u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[50u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[51u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[51u8])}),
             YamlSequenceEndEvent,
             YamlDocumentEndEvent(true),
             YamlStreamEndEvent
@@ -304,15 +429,15 @@ mod test {
     #[test]
     fn test_byte_parser_mapping() {
         let data = "{\"a\": 1, \"b\":2}";
-        let parser = parser::YamlByteParser::init(data.as_bytes());
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
         let expected = ~[
             YamlStreamStartEvent(ffi::YamlUtf8Encoding),
             YamlDocumentStartEvent(None, ~[], true),
             YamlMappingStartEvent(YamlSequenceParam{anchor: None, tag: None, implicit: true, style: ffi::YamlFlowSequenceStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[97u8], plain_implicit: false, quoted_implicit: true, style: ffi::YamlDoubleQuotedScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[98u8], plain_implicit: false, quoted_implicit: true, style: ffi::YamlDoubleQuotedScalarStyle}),
-            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[97u8], plain_implicit: false, quoted_implicit: true, style: ffi::YamlDoubleQuotedScalarStyle, repr: Some(~[34u8, 97u8, 34u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[49u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[49u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[98u8], plain_implicit: false, quoted_implicit: true, style: ffi::YamlDoubleQuotedScalarStyle, repr: Some(~[34u8, 98u8, 34u8])}),
+            YamlScalarEvent(YamlScalarParam{anchor: None, tag: None, value: ~[50u8], plain_implicit: true, quoted_implicit: false, style: ffi::YamlPlainScalarStyle, repr: Some(~[50u8])}),
             YamlMappingEndEvent,
             YamlDocumentEndEvent(true),
             YamlStreamEndEvent
@@ -341,7 +466,7 @@ mod test {
     #[test]
     fn test_parser_error() {
         let data = "\"ab";
-        let parser = parser::YamlByteParser::init(data.as_bytes());
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
         let mut stream = parser.parse();
 
         let stream_start = stream.next_event();
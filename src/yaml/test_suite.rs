@@ -0,0 +1,121 @@
+use ffi;
+use event::*;
+use parser::{YamlParser, YamlEventStream, YamlError, YamlMark};
+use parser::YamlWriterError;
+use std::io;
+
+// Render a parsed event stream into the line-based event notation used by the
+// official YAML test suite, so the crate's output can be diffed byte-for-byte
+// against the suite's expected `.event` files.
+pub fn write_events<P:YamlParser>(stream: &mut YamlEventStream<P>, writer: &mut Writer) -> Result<(), YamlError> {
+    loop {
+        match stream.next_event() {
+            Ok(YamlNoEvent) => return Ok(()),
+            Ok(evt) => match write_event(evt, writer) {
+                Ok(()) => {},
+                Err(err) => return Err(writer_error(err))
+            },
+            Err(err) => return Err(err)
+        }
+    }
+}
+
+fn write_event(event: YamlEvent, writer: &mut Writer) -> io::IoResult<()> {
+    let mut line: ~[u8] = ~[];
+    match event {
+        YamlStreamStartEvent(..) => line.push_all(bytes!("+STR")),
+        YamlStreamEndEvent => line.push_all(bytes!("-STR")),
+        YamlDocumentStartEvent(_, _, implicit) => line.push_all(if implicit { bytes!("+DOC") } else { bytes!("+DOC ---") }),
+        YamlDocumentEndEvent(implicit) => line.push_all(if implicit { bytes!("-DOC") } else { bytes!("-DOC ...") }),
+        YamlMappingStartEvent(..) => line.push_all(bytes!("+MAP")),
+        YamlMappingEndEvent => line.push_all(bytes!("-MAP")),
+        YamlSequenceStartEvent(..) => line.push_all(bytes!("+SEQ")),
+        YamlSequenceEndEvent => line.push_all(bytes!("-SEQ")),
+        YamlAliasEvent(anchor) => {
+            line.push_all(bytes!("=ALI *"));
+            line.push_all(anchor.as_bytes());
+        },
+        YamlScalarEvent(scalar) => scalar_line(&mut line, scalar),
+        YamlNoEvent => return Ok(())
+    }
+    line.push(b'\n');
+    writer.write(line)
+}
+
+fn scalar_line(line: &mut ~[u8], scalar: YamlScalarParam) {
+    line.push_all(bytes!("=VAL"));
+    match scalar.anchor {
+        Some(ref anchor) => {
+            line.push_all(bytes!(" &"));
+            line.push_all(anchor.as_bytes());
+        },
+        None => {}
+    }
+    match scalar.tag {
+        Some(ref tag) => {
+            line.push_all(bytes!(" <"));
+            line.push_all(tag.as_bytes());
+            line.push(b'>');
+        },
+        None => {}
+    }
+
+    let sigil = match scalar.style {
+        ffi::YamlSingleQuotedScalarStyle => b'\'',
+        ffi::YamlDoubleQuotedScalarStyle => b'"',
+        ffi::YamlLiteralScalarStyle => b'|',
+        ffi::YamlFoldedScalarStyle => b'>',
+        _ => b':'
+    };
+    line.push(b' ');
+    line.push(sigil);
+    escape_into(line, scalar.value);
+}
+
+// The test-suite notation escapes only backslash and the three C whitespace
+// escapes; every other byte is emitted verbatim so multibyte UTF-8 scalars
+// survive a byte-for-byte diff.
+fn escape_into(out: &mut ~[u8], value: &[u8]) {
+    for &byte in value.iter() {
+        match byte {
+            b'\\' => out.push_all(bytes!("\\\\")),
+            b'\n' => out.push_all(bytes!("\\n")),
+            b'\t' => out.push_all(bytes!("\\t")),
+            b'\r' => out.push_all(bytes!("\\r")),
+            _ => out.push(byte)
+        }
+    }
+}
+
+fn writer_error(err: io::IoError) -> YamlError {
+    YamlError {
+        kind: YamlWriterError,
+        problem: Some(err.desc.to_owned()),
+        byte_offset: 0,
+        problem_mark: YamlMark { index: 0, line: 0, column: 0 },
+        context: None,
+        context_mark: YamlMark { index: 0, line: 0, column: 0 },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use parser;
+    use parser::YamlParser;
+    use test_suite;
+    use std::io::MemWriter;
+    use std::str;
+
+    #[test]
+    fn test_write_events() {
+        let data = "[1, 2, 3]";
+        let parser = parser::YamlByteParser::init(data.as_bytes()).unwrap();
+        let mut stream = parser.parse();
+
+        let mut writer = MemWriter::new();
+        test_suite::write_events(&mut stream, &mut writer).unwrap();
+
+        let expected = "+STR\n+DOC\n+SEQ\n=VAL :1\n=VAL :2\n=VAL :3\n-SEQ\n-DOC\n-STR\n";
+        assert_eq!(expected, str::from_utf8(writer.get_ref()).unwrap());
+    }
+}